@@ -1,7 +1,9 @@
+use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::io::{self, stdout, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 use crossterm::{
     cursor,
@@ -9,6 +11,7 @@ use crossterm::{
     style::{Stylize},
     terminal::{self, ClearType},
 };
+use rayon::prelude::*;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
 enum EntryType {
@@ -36,7 +39,224 @@ struct EntryInfo {
     size: Option<u64>,
 }
 
-fn get_entry_info(entry: fs::DirEntry) -> io::Result<EntryInfo> {
+/// How a file's size is accounted for.
+///
+/// `Apparent` is the logical byte length (`metadata.len()`), which overcounts
+/// sparse files and ignores block rounding. `Allocated` reflects the blocks the
+/// file actually occupies on disk, matching `du`'s default accounting.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SizeMode {
+    Apparent,
+    Allocated,
+}
+
+/// Name-based filtering applied to both the listing and the recursive byte
+/// tally, so an excluded subtree neither appears nor contributes to its
+/// parent's size.
+struct Filters {
+    exclude: Vec<glob::Pattern>,
+    no_hidden: bool,
+}
+
+impl Filters {
+    /// Whether an entry with the given file name should be traversed.
+    fn accepts(&self, name: &str) -> bool {
+        if self.no_hidden && name.starts_with('.') {
+            return false;
+        }
+        !self.exclude.iter().any(|pattern| pattern.matches(name))
+    }
+}
+
+/// Set of `(dev, ino)` keys already counted, shared across every recursive and
+/// sibling call in a single scan so a file reachable through multiple hard
+/// links contributes its bytes exactly once. `None` when `--count-links`
+/// disables deduplication.
+type VisitedInodes = Arc<Mutex<HashSet<(u64, u64)>>>;
+
+/// Per-entry errors collected during a `--persistent` scan, shared across the
+/// rayon fan-out. `None` outside persistent mode, where the first error
+/// propagates instead.
+type Warnings = Arc<Mutex<Vec<(PathBuf, io::Error)>>>;
+
+/// Record `error` for `path` when running persistently (and report zero bytes
+/// for the offending entry), or propagate it otherwise.
+fn handle_error(path: &Path, error: io::Error, warnings: Option<&Warnings>) -> io::Result<u64> {
+    match warnings {
+        Some(warnings) => {
+            warnings.lock().unwrap().push((path.to_path_buf(), error));
+            Ok(0)
+        }
+        None => Err(error),
+    }
+}
+
+/// Print a summary of the paths skipped during a persistent scan.
+fn display_warnings(warnings: Option<&Warnings>) {
+    if let Some(warnings) = warnings {
+        let warnings = warnings.lock().unwrap();
+        if !warnings.is_empty() {
+            println!("\nSkipped {} path(s):", warnings.len());
+            for (path, error) in warnings.iter() {
+                println!("  {}: {}", path.display(), error);
+            }
+        }
+    }
+}
+
+/// Size of a single entry under the selected accounting mode.
+fn entry_size(metadata: &fs::Metadata, path: &Path, mode: SizeMode) -> u64 {
+    match mode {
+        SizeMode::Apparent => metadata.len(),
+        SizeMode::Allocated => allocated_size(metadata, path),
+    }
+}
+
+/// Size a file contributes to a total, honoring hard-link deduplication. When
+/// `visited` is `Some`, the first time a `(dev, ino)` key is seen the file's
+/// bytes are counted and the key recorded; subsequent links return zero.
+fn counted_file_size(
+    metadata: &fs::Metadata,
+    path: &Path,
+    mode: SizeMode,
+    visited: Option<&VisitedInodes>,
+) -> u64 {
+    if let Some(visited) = visited {
+        if let Some(key) = file_key(metadata, path) {
+            let mut seen = visited.lock().unwrap();
+            if !seen.insert(key) {
+                return 0;
+            }
+        }
+    }
+    entry_size(metadata, path, mode)
+}
+
+#[cfg(unix)]
+fn file_key(metadata: &fs::Metadata, _path: &Path) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+fn file_key(_metadata: &fs::Metadata, path: &Path) -> Option<(u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct ByHandleFileInformation {
+        dw_file_attributes: u32,
+        ft_creation_time: [u32; 2],
+        ft_last_access_time: [u32; 2],
+        ft_last_write_time: [u32; 2],
+        dw_volume_serial_number: u32,
+        n_file_size_high: u32,
+        n_file_size_low: u32,
+        n_number_of_links: u32,
+        n_file_index_high: u32,
+        n_file_index_low: u32,
+    }
+
+    const GENERIC_READ: u32 = 0x8000_0000;
+    const FILE_SHARE_READ: u32 = 0x0000_0001;
+    const FILE_SHARE_WRITE: u32 = 0x0000_0002;
+    const FILE_SHARE_DELETE: u32 = 0x0000_0004;
+    const OPEN_EXISTING: u32 = 3;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    const INVALID_HANDLE_VALUE: isize = -1;
+
+    extern "system" {
+        fn CreateFileW(
+            lpFileName: *const u16,
+            dwDesiredAccess: u32,
+            dwShareMode: u32,
+            lpSecurityAttributes: *mut core::ffi::c_void,
+            dwCreationDisposition: u32,
+            dwFlagsAndAttributes: u32,
+            hTemplateFile: isize,
+        ) -> isize;
+        fn GetFileInformationByHandle(
+            hFile: isize,
+            lpFileInformation: *mut ByHandleFileInformation,
+        ) -> i32;
+        fn CloseHandle(hObject: isize) -> i32;
+    }
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    // Safety: `wide` is a valid NUL-terminated path; the handle is closed
+    // before returning.
+    unsafe {
+        let handle = CreateFileW(
+            wide.as_ptr(),
+            GENERIC_READ,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            std::ptr::null_mut(),
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            0,
+        );
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        let mut info: ByHandleFileInformation = std::mem::zeroed();
+        let ok = GetFileInformationByHandle(handle, &mut info);
+        CloseHandle(handle);
+        if ok == 0 {
+            return None;
+        }
+        let index = (u64::from(info.n_file_index_high) << 32) | u64::from(info.n_file_index_low);
+        Some((u64::from(info.dw_volume_serial_number), index))
+    }
+}
+
+#[cfg(unix)]
+fn allocated_size(metadata: &fs::Metadata, _path: &Path) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    // `blocks()` counts 512-byte units regardless of the filesystem block size.
+    metadata.blocks() * 512
+}
+
+#[cfg(windows)]
+fn allocated_size(metadata: &fs::Metadata, path: &Path) -> u64 {
+    use std::os::windows::ffi::OsStrExt;
+
+    extern "system" {
+        fn GetCompressedFileSizeW(
+            lpFileName: *const u16,
+            lpFileSizeHigh: *mut u32,
+        ) -> u32;
+    }
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut high: u32 = 0;
+    // Safety: `wide` is a valid NUL-terminated path and `high` is a valid
+    // out-pointer for the duration of the call.
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    if low == u32::MAX {
+        // INVALID_FILE_SIZE with no compressed size available; fall back to
+        // the apparent length rather than reporting zero.
+        metadata.len()
+    } else {
+        (u64::from(high) << 32) | u64::from(low)
+    }
+}
+
+fn get_entry_info(
+    entry: fs::DirEntry,
+    mode: SizeMode,
+    visited: Option<&VisitedInodes>,
+    filters: &Filters,
+    warnings: Option<&Warnings>,
+) -> io::Result<EntryInfo> {
     let path = entry.path();
     let metadata = entry.metadata()?;
     let entry_type = if metadata.is_file() {
@@ -51,9 +271,9 @@ fn get_entry_info(entry: fs::DirEntry) -> io::Result<EntryInfo> {
     };
 
     let size = if entry_type == EntryType::File {
-        Some(metadata.len())
+        Some(counted_file_size(&metadata, &path, mode, visited))
     } else if entry_type == EntryType::Directory {
-        Some(get_directory_size(&path)?)
+        Some(get_directory_size(&path, mode, visited, filters, warnings)?)
     } else {
         None
     };
@@ -65,29 +285,246 @@ fn get_entry_info(entry: fs::DirEntry) -> io::Result<EntryInfo> {
     })
 }
 
-fn get_directory_size(path: &Path) -> io::Result<u64> {
-    let mut total_size = 0;
+fn get_directory_size(
+    path: &Path,
+    mode: SizeMode,
+    visited: Option<&VisitedInodes>,
+    filters: &Filters,
+    warnings: Option<&Warnings>,
+) -> io::Result<u64> {
+    let read_dir = match fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(error) => return handle_error(path, error, warnings),
+    };
+
+    // Collect the readable, non-excluded entries. A read error on an individual
+    // entry is recorded and skipped (persistent) or propagated otherwise.
+    let mut entries = Vec::new();
+    for entry in read_dir {
+        match entry {
+            Ok(entry) => {
+                if filters.accepts(&entry.file_name().to_string_lossy()) {
+                    entries.push(entry);
+                }
+            }
+            Err(error) => {
+                handle_error(path, error, warnings)?;
+            }
+        }
+    }
 
-    for entry in fs::read_dir(path)? {
-        let entry = entry?;
-        let metadata = entry.metadata()?;
+    // Fan each subdirectory's recursive size out onto the rayon thread pool
+    // while files are summed in place. The per-entry `io::Result`s fold back
+    // into a single `io::Result<u64>`, so the return type is unchanged.
+    // The `visited` set is shared (behind a mutex) across every branch so a
+    // hard-linked file counts once no matter where it is reached; `warnings`
+    // collects per-entry failures the same way.
+    entries
+        .par_iter()
+        .map(|entry| {
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(error) => return handle_error(&entry.path(), error, warnings),
+            };
+            if metadata.is_file() {
+                Ok(counted_file_size(&metadata, &entry.path(), mode, visited))
+            } else if metadata.is_dir() {
+                get_directory_size(&entry.path(), mode, visited, filters, warnings)
+            } else {
+                Ok(0)
+            }
+        })
+        .sum()
+}
+
+/// A directory-tree node built during a single traversal for `--tree`
+/// rendering. Leaf files have an empty `children` vector.
+struct Node {
+    name: String,
+    size: u64,
+    children: Vec<Node>,
+}
+
+/// Recursively build a [`Node`] tree rooted at `path`, honoring the size mode,
+/// hard-link dedup, and name filters. Sizes are accumulated from the full
+/// subtree regardless of the later display depth.
+fn build_node(
+    path: &Path,
+    name: String,
+    mode: SizeMode,
+    visited: Option<&VisitedInodes>,
+    filters: &Filters,
+    warnings: Option<&Warnings>,
+) -> io::Result<Node> {
+    let mut children = Vec::new();
+    let mut size = 0;
+
+    let read_dir = match fs::read_dir(path) {
+        Ok(read_dir) => read_dir,
+        Err(error) => {
+            handle_error(path, error, warnings)?;
+            return Ok(Node {
+                name,
+                size: 0,
+                children,
+            });
+        }
+    };
+
+    for entry in read_dir {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                handle_error(path, error, warnings)?;
+                continue;
+            }
+        };
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if !filters.accepts(&file_name) {
+            continue;
+        }
+
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                handle_error(&entry.path(), error, warnings)?;
+                continue;
+            }
+        };
         if metadata.is_file() {
-            total_size += metadata.len();
+            let file_size = counted_file_size(&metadata, &entry.path(), mode, visited);
+            size += file_size;
+            children.push(Node {
+                name: file_name,
+                size: file_size,
+                children: Vec::new(),
+            });
         } else if metadata.is_dir() {
-            total_size += get_directory_size(&entry.path())?;
+            let child = build_node(&entry.path(), file_name, mode, visited, filters, warnings)?;
+            size += child.size;
+            children.push(child);
+        }
+    }
+
+    Ok(Node {
+        name,
+        size,
+        children,
+    })
+}
+
+/// A child slot in the rendered tree: either a real node or the synthetic
+/// bucket that `--aggr` collapses small children into.
+enum TreeItem<'a> {
+    Real(&'a Node),
+    Aggregated(u64),
+}
+
+impl TreeItem<'_> {
+    fn size(&self) -> u64 {
+        match self {
+            TreeItem::Real(node) => node.size,
+            TreeItem::Aggregated(size) => *size,
         }
     }
+}
 
-    Ok(total_size)
+/// Print a whole hierarchy at once with box-drawing connectors, stopping at
+/// `depth` levels below the root.
+fn render_tree(root: &Node, depth: usize, aggr: u64, unit: &Unit) {
+    println!("{} [{}]", root.name, format_size(root.size, unit));
+    render_children(root, "", depth, aggr, unit);
 }
 
-fn get_entries_info(dir_path: &str) -> io::Result<Vec<EntryInfo>> {
+fn render_children(node: &Node, prefix: &str, depth_left: usize, aggr: u64, unit: &Unit) {
+    if depth_left == 0 {
+        return;
+    }
+
+    // Collapse children below the aggregation threshold into one bucket.
+    let mut displayed: Vec<TreeItem> = Vec::new();
+    let mut aggregated = 0;
+    for child in &node.children {
+        if aggr > 0 && child.size < aggr {
+            aggregated += child.size;
+        } else {
+            displayed.push(TreeItem::Real(child));
+        }
+    }
+    displayed.sort_by(|a, b| b.size().cmp(&a.size()));
+    if aggregated > 0 {
+        displayed.push(TreeItem::Aggregated(aggregated));
+    }
+
+    // Progress bars scale relative to this node's own size.
+    let parent_size = node.size.max(1);
+    let count = displayed.len();
+    for (index, item) in displayed.iter().enumerate() {
+        let is_last = index + 1 == count;
+        let connector = if is_last { "└── " } else { "├── " };
+        let bar_length = 20;
+        let progress =
+            (item.size() as f64 / parent_size as f64 * bar_length as f64) as usize;
+        let bar = format!("{:=<1$}", "", progress).cyan();
+
+        match item {
+            TreeItem::Real(child) => {
+                println!(
+                    "{}{}{} [{}] [{}]",
+                    prefix,
+                    connector,
+                    child.name,
+                    bar,
+                    format_size(child.size, unit)
+                );
+                let child_prefix =
+                    format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+                render_children(child, &child_prefix, depth_left - 1, aggr, unit);
+            }
+            TreeItem::Aggregated(size) => {
+                println!(
+                    "{}{}<aggregated> [{}] [{}]",
+                    prefix,
+                    connector,
+                    bar,
+                    format_size(*size, unit)
+                );
+            }
+        }
+    }
+}
+
+fn get_entries_info(
+    dir_path: &str,
+    mode: SizeMode,
+    dedup: bool,
+    filters: &Filters,
+    warnings: Option<&Warnings>,
+) -> io::Result<Vec<EntryInfo>> {
     let mut entries_info = Vec::new();
 
+    // One shared set per scan, or `None` when `--count-links` is set.
+    let visited: Option<VisitedInodes> =
+        dedup.then(|| Arc::new(Mutex::new(HashSet::new())));
+
     for entry in fs::read_dir(dir_path)? {
-        let entry = entry?;
-        let entry_info = get_entry_info(entry)?;
-        entries_info.push(entry_info);
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                handle_error(Path::new(dir_path), error, warnings)?;
+                continue;
+            }
+        };
+        if !filters.accepts(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let entry_path = entry.path();
+        match get_entry_info(entry, mode, visited.as_ref(), filters, warnings) {
+            Ok(entry_info) => entries_info.push(entry_info),
+            Err(error) => {
+                handle_error(&entry_path, error, warnings)?;
+            }
+        }
     }
 
     entries_info.sort_by(|a, b| {
@@ -101,7 +538,7 @@ fn get_entries_info(dir_path: &str) -> io::Result<Vec<EntryInfo>> {
     Ok(entries_info)
 }
 
-fn display_entries_info(entries_info: &[EntryInfo]) {
+fn display_entries_info(entries_info: &[EntryInfo], unit: &Unit, warnings: Option<&Warnings>) {
     let total_entries = entries_info.len();
     let max_size = entries_info
         .iter()
@@ -120,7 +557,7 @@ fn display_entries_info(entries_info: &[EntryInfo]) {
         if let Some(size) = entry_info.size {
             let progress = (size as f64 / max_size as f64 * progress_bar_length as f64) as usize;
             let progress_bar = format!("{:=<1$}", "", progress).cyan();
-            let size_str = format_size(size);
+            let size_str = format_size(size, unit);
             println!(
                 "{:<3} {} [{}] {} [{}]",
                 index + 1,
@@ -135,24 +572,81 @@ fn display_entries_info(entries_info: &[EntryInfo]) {
     }
 
     println!("\nTotal entries: {}", total_entries);
+
+    display_warnings(warnings);
 }
 
-fn format_size(size: u64) -> String {
-    const KB: f64 = 1024.0;
-    const MB: f64 = KB * 1024.0;
-    const GB: f64 = MB * 1024.0;
-    const TB: f64 = GB * 1024.0;
-
-    if size < KB as u64 {
-        format!("{} B", size)
-    } else if size < MB as u64 {
-        format!("{:.2} KB", size as f64 / KB)
-    } else if size < GB as u64 {
-        format!("{:.2} MB", size as f64 / MB)
-    } else if size < TB as u64 {
-        format!("{:.2} GB", size as f64 / GB)
-    } else {
-        format!("{:.2} TB", size as f64 / TB)
+/// How sizes are rendered in the listing.
+enum Unit {
+    /// Auto-scale through the given step `base` (1024 or 1000), picking binary
+    /// (`KiB`) or SI (`kB`) suffixes based on `si`.
+    Scaled { base: f64, si: bool },
+    /// Raw integer bytes, no scaling.
+    Bytes,
+    /// A single fixed unit applied to every row.
+    Fixed { divisor: f64, suffix: &'static str },
+}
+
+/// Parse a `--unit` spec (`b`, `kb`/`ki`, `mb`/`mi`, `gb`/`gi`, `tb`/`ti`)
+/// into a fixed `Unit`. The `*b` forms are SI (1000-based), the `*i` forms are
+/// binary (1024-based).
+fn parse_unit(spec: &str) -> Option<Unit> {
+    const K: f64 = 1000.0;
+    const KI: f64 = 1024.0;
+    Some(match spec.to_ascii_lowercase().as_str() {
+        "b" => Unit::Bytes,
+        "kb" => Unit::Fixed { divisor: K, suffix: "kB" },
+        "mb" => Unit::Fixed { divisor: K * K, suffix: "MB" },
+        "gb" => Unit::Fixed { divisor: K * K * K, suffix: "GB" },
+        "tb" => Unit::Fixed { divisor: K * K * K * K, suffix: "TB" },
+        "ki" => Unit::Fixed { divisor: KI, suffix: "KiB" },
+        "mi" => Unit::Fixed { divisor: KI * KI, suffix: "MiB" },
+        "gi" => Unit::Fixed { divisor: KI * KI * KI, suffix: "GiB" },
+        "ti" => Unit::Fixed { divisor: KI * KI * KI * KI, suffix: "TiB" },
+        _ => return None,
+    })
+}
+
+/// Parse a human-friendly size such as `1M` or `512K` into a byte count,
+/// using binary (1024-based) multipliers. A bare number is taken as bytes.
+fn parse_size(spec: &str) -> Option<u64> {
+    let spec = spec.trim();
+    let (digits, multiplier) = match spec.chars().last() {
+        Some('K') | Some('k') => (&spec[..spec.len() - 1], 1024),
+        Some('M') | Some('m') => (&spec[..spec.len() - 1], 1024 * 1024),
+        Some('G') | Some('g') => (&spec[..spec.len() - 1], 1024 * 1024 * 1024),
+        Some('T') | Some('t') => (&spec[..spec.len() - 1], 1024u64 * 1024 * 1024 * 1024),
+        _ => (spec, 1),
+    };
+    digits.trim().parse::<u64>().ok().map(|n| n * multiplier)
+}
+
+fn format_size(size: u64, unit: &Unit) -> String {
+    match unit {
+        Unit::Bytes => format!("{} B", size),
+        Unit::Fixed { divisor, suffix } => {
+            format!("{:.2} {}", size as f64 / divisor, suffix)
+        }
+        Unit::Scaled { base, si } => {
+            let suffixes = if *si {
+                ["B", "kB", "MB", "GB", "TB"]
+            } else {
+                ["B", "KiB", "MiB", "GiB", "TiB"]
+            };
+
+            let mut value = size as f64;
+            let mut index = 0;
+            while value >= *base && index < suffixes.len() - 1 {
+                value /= *base;
+                index += 1;
+            }
+
+            if index == 0 {
+                format!("{} {}", size, suffixes[0])
+            } else {
+                format!("{:.2} {}", value, suffixes[index])
+            }
+        }
     }
 }
 
@@ -180,27 +674,151 @@ fn prompt_user() -> Result<isize, ()> {
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        println!("Usage: cargo run <directory_path>");
+
+    let mut dir_path: Option<String> = None;
+    let mut jobs: Option<usize> = None;
+    let mut size_mode = SizeMode::Apparent;
+    let mut dedup = true;
+    let mut exclude: Vec<glob::Pattern> = Vec::new();
+    let mut no_hidden = false;
+    let mut unit = Unit::Scaled { base: 1024.0, si: false };
+    let mut tree = false;
+    let mut depth = usize::MAX;
+    let mut aggr = 0u64;
+    let mut persistent = false;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--jobs" => match iter
+                .next()
+                .and_then(|n| n.parse::<usize>().ok())
+                .filter(|&n| n > 0)
+            {
+                Some(n) => jobs = Some(n),
+                None => {
+                    println!("Error: --jobs requires a positive integer");
+                    return;
+                }
+            },
+            "--usage" => size_mode = SizeMode::Allocated,
+            "--count-links" => dedup = false,
+            "--exclude" => match iter.next() {
+                Some(pattern) => match glob::Pattern::new(pattern) {
+                    Ok(pattern) => exclude.push(pattern),
+                    Err(error) => {
+                        println!("Error: invalid --exclude pattern: {}", error);
+                        return;
+                    }
+                },
+                None => {
+                    println!("Error: --exclude requires a pattern");
+                    return;
+                }
+            },
+            "--no-hidden" => no_hidden = true,
+            "--base-two" => unit = Unit::Scaled { base: 1024.0, si: false },
+            "--si" => unit = Unit::Scaled { base: 1000.0, si: true },
+            "--bytes" => unit = Unit::Bytes,
+            "--unit" => match iter.next().and_then(|spec| parse_unit(spec)) {
+                Some(parsed) => unit = parsed,
+                None => {
+                    println!("Error: --unit requires one of b, kb, ki, mb, mi, gb, gi, tb, ti");
+                    return;
+                }
+            },
+            "--persistent" => persistent = true,
+            "--tree" => tree = true,
+            "--depth" => match iter.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) => depth = n,
+                None => {
+                    println!("Error: --depth requires a non-negative integer");
+                    return;
+                }
+            },
+            "--aggr" => match iter.next().and_then(|spec| parse_size(spec)) {
+                Some(bytes) => aggr = bytes,
+                None => {
+                    println!("Error: --aggr requires a size like 1M or 512K");
+                    return;
+                }
+            },
+            other => dir_path = Some(other.to_string()),
+        }
+    }
+
+    let current_dir = match dir_path {
+        Some(path) => path,
+        None => {
+            println!(
+                "Usage: cargo run <directory_path> [--jobs N] [--usage] \
+                 [--count-links] [--exclude PATTERN]... [--no-hidden] \
+                 [--base-two | --si | --bytes | --unit UNIT] \
+                 [--tree [--depth N] [--aggr SIZE]] [--persistent]"
+            );
+            return;
+        }
+    };
+
+    // Cap the rayon pool size when requested; otherwise rayon picks a
+    // default based on the number of logical CPUs.
+    if let Some(n) = jobs {
+        if rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build_global()
+            .is_err()
+        {
+            println!("Error: failed to configure thread pool");
+            return;
+        }
+    }
+
+    let filters = Filters { exclude, no_hidden };
+
+    // Non-interactive tree rendering: build the whole hierarchy once and print
+    // it, rather than stepping through one directory per screen.
+    if tree {
+        let path = Path::new(&current_dir);
+        let visited: Option<VisitedInodes> =
+            dedup.then(|| Arc::new(Mutex::new(HashSet::new())));
+        let warnings: Option<Warnings> =
+            persistent.then(|| Arc::new(Mutex::new(Vec::new())));
+        match build_node(
+            path,
+            current_dir.clone(),
+            size_mode,
+            visited.as_ref(),
+            &filters,
+            warnings.as_ref(),
+        ) {
+            Ok(root) => {
+                render_tree(&root, depth, aggr, &unit);
+                display_warnings(warnings.as_ref());
+            }
+            Err(error) => println!("Error: {}", error),
+        }
         return;
     }
 
-    let mut current_dir = args[1].clone();
+    let mut current_dir = current_dir;
     let mut dir_stack = vec![current_dir.clone()];
 
     loop {
         clear_console();
         println!("Analyzing entries in directory: {}\n", current_dir);
 
-        let entries_info = match get_entries_info(&current_dir) {
-            Ok(entries_info) => entries_info,
-            Err(error) => {
-                println!("Error: {}", error);
-                return;
-            }
-        };
+        let warnings: Option<Warnings> =
+            persistent.then(|| Arc::new(Mutex::new(Vec::new())));
+        let entries_info =
+            match get_entries_info(&current_dir, size_mode, dedup, &filters, warnings.as_ref()) {
+                Ok(entries_info) => entries_info,
+                Err(error) => {
+                    println!("Error: {}", error);
+                    return;
+                }
+            };
 
-        display_entries_info(&entries_info);
+        display_entries_info(&entries_info, &unit, warnings.as_ref());
 
         println!();
         let choice = prompt_user().unwrap();